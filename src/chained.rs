@@ -1,103 +1,207 @@
-use std::hash::DefaultHasher;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
 use std::hash::Hash;
-use std::hash::Hasher;
 
 const LOAD_FACTOR: f64 = 0.75;
 
-struct Bucket<K, V> {
-    data: Vec<(K, V)>,
+/// A stable handle to an entry in a [`Chained`] map, returned by [`Chained::insert_full`].
+///
+/// An `EntryId` stays valid across resizes and other insertions/removals, so it can be
+/// stored elsewhere (e.g. as a compact node id in an adjacency list) and dereferenced
+/// later via [`Chained::get_by_id`] without re-hashing the original key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryId(usize);
+
+struct Bucket {
+    data: Vec<(u64, EntryId)>,
 }
 
-impl<K, V> Bucket<K, V> {
-    fn new() -> Bucket<K, V>
-    where
-        K: Eq + Hash,
-    {
+impl Bucket {
+    fn new() -> Bucket {
         Bucket { data: Vec::new() }
     }
-}
 
-impl<K, V> Bucket<K, V> {
-    fn iter(&self) -> impl Iterator<Item = &(K, V)> {
-        self.data.iter()
+    fn push(&mut self, hash: u64, id: EntryId) {
+        self.data.push((hash, id));
     }
+}
 
-    fn iter_mut(&mut self) -> impl Iterator<Item = &mut (K, V)> {
-        self.data.iter_mut()
-    }
+pub struct Chained<K, V, S = RandomState> {
+    buckets: Vec<Bucket>,
+    entries: Vec<Option<(K, V)>>,
+    free_list: Vec<usize>,
+    len: usize,
+    hasher: S,
+}
 
-    fn push(&mut self, key: K, value: V) {
-        self.data.push((key, value));
+impl<K, V> Chained<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
     }
 }
 
-pub struct Chained<K, V> {
-    buckets: Vec<Bucket<K, V>>,
-    len: usize,
+impl<K, V> Default for Chained<K, V, RandomState> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<K, V> Chained<K, V> {
-    pub fn new() -> Self {
+impl<K, V, S> Chained<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
         Chained {
             buckets: Vec::new(),
+            entries: Vec::new(),
+            free_list: Vec::new(),
             len: 0,
+            hasher,
         }
     }
 }
 
-impl<K, V> Default for Chained<K, V> {
-    fn default() -> Self {
-        Self::new()
+impl<K, V, S> Chained<K, V, S>
+where
+    K: Eq + Hash,
+{
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut buckets = Vec::with_capacity(capacity);
+        for _ in 0..capacity {
+            buckets.push(Bucket::new());
+        }
+        Chained {
+            buckets,
+            entries: Vec::new(),
+            free_list: Vec::new(),
+            len: 0,
+            hasher,
+        }
     }
 }
 
-impl<K, V> Chained<K, V>
+impl<K, V, S> Chained<K, V, S>
 where
     K: Hash + Eq,
+    S: BuildHasher,
 {
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_full(key, value).1
+    }
+
+    /// Inserts a key-value pair and returns a stable [`EntryId`] for it alongside any
+    /// value it replaced. The id remains valid until the entry is removed, even across
+    /// resizes and unrelated insertions.
+    pub fn insert_full(&mut self, key: K, value: V) -> (EntryId, Option<V>) {
         if self.is_empty() || self.len as f64 >= self.buckets.len() as f64 * LOAD_FACTOR {
             self.resize();
         }
 
-        let n_buckets = self.buckets.len();
-        let bucket_index = Self::bucket_index(&key, n_buckets);
-        let bucket = &mut self.buckets[bucket_index];
+        let hash = self.hasher.hash_one(&key);
+        let bucket_index = Self::bucket_index(hash, self.buckets.len());
+        let existing_id = self.buckets[bucket_index]
+            .data
+            .iter()
+            .find_map(|(h, id)| (*h == hash && self.key_at(*id) == Some(&key)).then_some(*id));
 
-        let x = bucket.iter_mut().find(|(k, _)| k == &key);
-        if let Some((_, v)) = x {
-            let old_value = std::mem::replace(v, value);
-            Some(old_value)
+        if let Some(id) = existing_id {
+            let old_value = self.entries[id.0]
+                .replace((key, value))
+                .map(|(_, v)| v)
+                .expect("existing_id refers to a live entry");
+            (id, Some(old_value))
         } else {
-            bucket.push(key, value);
+            let id = self.alloc_entry(key, value);
+            self.buckets[bucket_index].push(hash, id);
             self.len += 1;
-            None
+            (id, None)
         }
     }
 
     pub fn remove(&mut self, key: K) -> Option<V> {
-        let n_buckets = self.buckets.len();
-        let bucket_index = Self::bucket_index(&key, n_buckets);
-        let bucket = &mut self.buckets[bucket_index];
-
-        let i = bucket.iter().position(|(k, _)| k == &key)?;
-        let (_, v) = bucket.data.swap_remove(i);
+        let hash = self.hasher.hash_one(&key);
+        let bucket_index = Self::bucket_index(hash, self.buckets.len());
+        let pos = self.buckets[bucket_index]
+            .data
+            .iter()
+            .position(|(h, id)| *h == hash && self.key_at(*id) == Some(&key))?;
+        let (_, id) = self.buckets[bucket_index].data.swap_remove(pos);
 
-        self.len -= 1;
-        Some(v)
+        self.free_entry(id)
     }
 
     pub fn get(&self, key: K) -> Option<&V> {
-        let n_buckets = self.buckets.len();
-        let bucket_index = Self::bucket_index(&key, n_buckets);
-        let bucket = &self.buckets[bucket_index];
-        bucket.iter().find(|(k, _)| k == &key).map(|(_, v)| v)
+        let hash = self.hasher.hash_one(&key);
+        let bucket_index = Self::bucket_index(hash, self.buckets.len());
+        let id = self.buckets[bucket_index]
+            .data
+            .iter()
+            .find(|(h, id)| *h == hash && self.key_at(*id) == Some(&key))
+            .map(|(_, id)| *id)?;
+        self.get_by_id(id)
     }
 
     pub fn contains_key(&self, key: K) -> bool {
         self.get(key).is_some()
     }
 
+    /// Returns the value behind a previously issued [`EntryId`], or `None` if it was removed.
+    pub fn get_by_id(&self, id: EntryId) -> Option<&V> {
+        self.entries.get(id.0)?.as_ref().map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the value behind a previously issued [`EntryId`].
+    pub fn get_mut_by_id(&mut self, id: EntryId) -> Option<&mut V> {
+        self.entries.get_mut(id.0)?.as_mut().map(|(_, v)| v)
+    }
+
+    /// Removes the entry behind a previously issued [`EntryId`], freeing it for reuse.
+    pub fn remove_by_id(&mut self, id: EntryId) -> Option<V> {
+        let key = self.key_at(id)?;
+        let hash = self.hasher.hash_one(key);
+        let bucket_index = Self::bucket_index(hash, self.buckets.len());
+        self.remove_at(id, bucket_index)
+    }
+
+    /// Removes `id` from `bucket_index`, which the caller must already have derived
+    /// from this entry's hash (e.g. [`OccupiedEntry::remove`], which resolved its
+    /// bucket when `entry()` was called, so it doesn't need to re-hash the key here).
+    fn remove_at(&mut self, id: EntryId, bucket_index: usize) -> Option<V> {
+        if let Some(pos) = self.buckets[bucket_index]
+            .data
+            .iter()
+            .position(|(_, bucket_id)| *bucket_id == id)
+        {
+            self.buckets[bucket_index].data.swap_remove(pos);
+        }
+
+        self.free_entry(id)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        if self.is_empty() || self.len as f64 >= self.buckets.len() as f64 * LOAD_FACTOR {
+            self.resize();
+        }
+
+        let hash = self.hasher.hash_one(&key);
+        let bucket_index = Self::bucket_index(hash, self.buckets.len());
+        let existing_id = self.buckets[bucket_index]
+            .data
+            .iter()
+            .find_map(|(h, id)| (*h == hash && self.key_at(*id) == Some(&key)).then_some(*id));
+
+        match existing_id {
+            Some(id) => Entry::Occupied(OccupiedEntry {
+                map: self,
+                id,
+                bucket_index,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                key,
+                hash,
+                bucket_index,
+            }),
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -106,10 +210,53 @@ where
         self.len == 0
     }
 
-    fn bucket_index(key: &K, n_buckets: usize) -> usize {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        let hash = hasher.finish();
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.entries.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut(self.iter_mut())
+    }
+
+    fn key_at(&self, id: EntryId) -> Option<&K> {
+        self.entries[id.0].as_ref().map(|(k, _)| k)
+    }
+
+    fn alloc_entry(&mut self, key: K, value: V) -> EntryId {
+        if let Some(index) = self.free_list.pop() {
+            self.entries[index] = Some((key, value));
+            EntryId(index)
+        } else {
+            let index = self.entries.len();
+            self.entries.push(Some((key, value)));
+            EntryId(index)
+        }
+    }
+
+    fn free_entry(&mut self, id: EntryId) -> Option<V> {
+        let (_, value) = self.entries[id.0].take()?;
+        self.free_list.push(id.0);
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn bucket_index(hash: u64, n_buckets: usize) -> usize {
         (hash % n_buckets as u64) as usize
     }
 
@@ -118,14 +265,14 @@ where
             0 => 1,
             n => n * 2,
         };
-        let mut new_buckets = Vec::<Bucket<K, V>>::with_capacity(target_size);
+        let mut new_buckets = Vec::with_capacity(target_size);
         for _ in 0..target_size {
             new_buckets.push(Bucket::new());
         }
         for bucket in self.buckets.iter_mut() {
-            for (key, value) in bucket.data.drain(..) {
-                let bucket_index = Self::bucket_index(&key, target_size);
-                new_buckets[bucket_index].push(key, value);
+            for (hash, id) in bucket.data.drain(..) {
+                let bucket_index = Self::bucket_index(hash, target_size);
+                new_buckets[bucket_index].push(hash, id);
             }
         }
 
@@ -133,6 +280,254 @@ where
     }
 }
 
+/// A view into a single entry of a [`Chained`] map, obtained via [`Chained::entry`].
+pub enum Entry<'a, K, V, S> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+pub struct OccupiedEntry<'a, K, V, S> {
+    map: &'a mut Chained<K, V, S>,
+    id: EntryId,
+    bucket_index: usize,
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn get(&self) -> &V {
+        self.map.entries[self.id.0]
+            .as_ref()
+            .map(|(_, v)| v)
+            .expect("occupied entry refers to a live id")
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.entries[self.id.0]
+            .as_mut()
+            .map(|(_, v)| v)
+            .expect("occupied entry refers to a live id")
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.entries[self.id.0]
+            .as_mut()
+            .map(|(_, v)| v)
+            .expect("occupied entry refers to a live id")
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.map
+            .remove_at(self.id, self.bucket_index)
+            .expect("occupied entry refers to a live id")
+    }
+}
+
+pub struct VacantEntry<'a, K, V, S> {
+    map: &'a mut Chained<K, V, S>,
+    key: K,
+    hash: u64,
+    bucket_index: usize,
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn insert(self, value: V) -> &'a mut V {
+        let id = self.map.alloc_entry(self.key, value);
+        self.map.buckets[self.bucket_index].push(self.hash, id);
+        self.map.len += 1;
+
+        self.map.entries[id.0]
+            .as_mut()
+            .map(|(_, v)| v)
+            .expect("just inserted")
+    }
+}
+
+/// Borrowing iterator over a [`Chained`] map's live entries.
+pub struct Iter<'a, K, V> {
+    inner: std::slice::Iter<'a, Option<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .find_map(|slot| slot.as_ref().map(|(k, v)| (k, v)))
+    }
+}
+
+/// Mutably borrowing iterator over a [`Chained`] map's live entries.
+pub struct IterMut<'a, K, V> {
+    inner: std::slice::IterMut<'a, Option<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .find_map(|slot| slot.as_mut().map(|(k, v)| (&*k, v)))
+    }
+}
+
+/// Owning iterator over a [`Chained`] map's live entries.
+pub struct IntoIter<K, V> {
+    inner: std::vec::IntoIter<Option<(K, V)>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find_map(|slot| slot)
+    }
+}
+
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, _)| k)
+    }
+}
+
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
+    }
+}
+
+pub struct ValuesMut<'a, K, V>(IterMut<'a, K, V>);
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a Chained<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut Chained<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> IntoIterator for Chained<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for Chained<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for Chained<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Chained::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,4 +559,135 @@ mod tests {
         assert!(chained.is_empty());
         assert_eq!(chained.get("foo"), None);
     }
+
+    #[test]
+    fn entry_or_insert() {
+        let mut chained = Chained::new();
+
+        *chained.entry("foo").or_insert(0) += 1;
+        *chained.entry("foo").or_insert(0) += 1;
+        assert_eq!(chained.get("foo"), Some(&2));
+        assert_eq!(chained.len(), 1);
+    }
+
+    #[test]
+    fn occupied_entry_remove() {
+        let mut chained = Chained::new();
+        chained.insert("foo", 1);
+        chained.insert("bar", 2);
+
+        let removed = match chained.entry("foo") {
+            Entry::Occupied(entry) => entry.remove(),
+            Entry::Vacant(_) => panic!("expected an occupied entry"),
+        };
+
+        assert_eq!(removed, 1);
+        assert_eq!(chained.get("foo"), None);
+        assert_eq!(chained.get("bar"), Some(&2));
+        assert_eq!(chained.len(), 1);
+    }
+
+    #[test]
+    fn entry_and_modify_or_default() {
+        let mut chained: Chained<&str, i32> = Chained::new();
+
+        chained.entry("foo").and_modify(|v| *v += 1).or_default();
+        assert_eq!(chained.get("foo"), Some(&0));
+
+        chained.entry("foo").and_modify(|v| *v += 1).or_default();
+        assert_eq!(chained.get("foo"), Some(&1));
+    }
+
+    #[test]
+    fn iterates_over_all_entries() {
+        let mut chained = Chained::new();
+        chained.insert("foo", 1);
+        chained.insert("bar", 2);
+        chained.insert("baz", 3);
+
+        let mut values: Vec<_> = chained.values().copied().collect();
+        values.sort_unstable();
+        assert_eq!(values, vec![1, 2, 3]);
+
+        for value in chained.values_mut() {
+            *value *= 10;
+        }
+        let mut keys: Vec<_> = chained.keys().copied().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec!["bar", "baz", "foo"]);
+        assert_eq!(chained.get("foo"), Some(&10));
+
+        let mut collected: Vec<_> = chained.into_iter().collect();
+        collected.sort_unstable();
+        assert_eq!(collected, vec![("bar", 20), ("baz", 30), ("foo", 10)]);
+    }
+
+    #[test]
+    fn collects_from_iterator_and_extends() {
+        let mut chained: Chained<&str, i32> = [("a", 1), ("b", 2)].into_iter().collect();
+        assert_eq!(chained.len(), 2);
+
+        chained.extend([("c", 3)]);
+        assert_eq!(chained.get("c"), Some(&3));
+        assert_eq!(chained.len(), 3);
+    }
+
+    #[test]
+    fn resize_reuses_cached_hashes_without_rehashing() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        #[derive(Default)]
+        struct CountingState {
+            inner: RandomState,
+            calls: Rc<Cell<usize>>,
+        }
+
+        impl BuildHasher for CountingState {
+            type Hasher = <RandomState as BuildHasher>::Hasher;
+
+            fn build_hasher(&self) -> Self::Hasher {
+                self.calls.set(self.calls.get() + 1);
+                self.inner.build_hasher()
+            }
+        }
+
+        let state = CountingState::default();
+        let calls = Rc::clone(&state.calls);
+        let mut chained = Chained::with_hasher(state);
+        for i in 0..64 {
+            chained.insert(i, i * 2);
+        }
+        // One hash per insert: the resizes along the way reuse the cached hashes.
+        assert_eq!(calls.get(), 64);
+        for i in 0..64 {
+            assert_eq!(chained.get(i), Some(&(i * 2)));
+        }
+    }
+
+    #[test]
+    fn entry_ids_stay_valid_across_inserts_and_removals() {
+        let mut chained = Chained::new();
+
+        let (foo_id, old) = chained.insert_full("foo".to_string(), 1);
+        assert_eq!(old, None);
+        let (bar_id, _) = chained.insert_full("bar".to_string(), 2);
+
+        for i in 0..64 {
+            chained.insert(format!("key{i}"), i);
+        }
+
+        assert_eq!(chained.get_by_id(foo_id), Some(&1));
+        assert_eq!(chained.get_by_id(bar_id), Some(&2));
+
+        *chained.get_mut_by_id(foo_id).unwrap() += 10;
+        assert_eq!(chained.get_by_id(foo_id), Some(&11));
+
+        assert_eq!(chained.remove_by_id(foo_id), Some(11));
+        assert_eq!(chained.get_by_id(foo_id), None);
+        assert_eq!(chained.get("foo".to_string()), None);
+
+        let (reused_id, _) = chained.insert_full("foo2".to_string(), 42);
+        assert_eq!(chained.get_by_id(reused_id), Some(&42));
+    }
 }
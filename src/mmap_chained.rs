@@ -0,0 +1,450 @@
+use std::collections::hash_map::RandomState;
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, Hash};
+use std::io;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+
+use memmap2::{MmapMut, MmapOptions};
+
+/// Configuration for a [`MmapChained`] bucket store, following the layout knobs exposed
+/// by Solana's `bucket_map`: buckets are spread round-robin across one or more backing
+/// drives, and probe work per operation is bounded so callers can detect when a bucket
+/// needs to grow instead of spinning indefinitely.
+pub struct MmapChainedConfig {
+    /// Directories buckets are round-robined across; each bucket lives in its own file.
+    pub drives: Vec<PathBuf>,
+    /// Number of buckets to create. Rounded up to the next power of two so buckets can
+    /// be indexed by the top bits of the hash with a simple shift.
+    pub bucket_count: usize,
+    /// Maximum number of records probed when placing a brand-new key before giving up.
+    /// Looking up an existing key always scans the whole bucket, since a prior insert
+    /// may have grown the bucket (and thus its probe range) after that key was placed.
+    pub max_search: usize,
+    /// Must be set to `true` to open a store. [`MmapChained`] only stores a key's `u64`
+    /// hash on disk, not the key itself (see [`Record`]'s doc comment), so two keys that
+    /// collide on that hash are silently treated as the same entry — a real correctness
+    /// divergence from [`Chained`](crate::chained::Chained), not just a memory-layout
+    /// detail. This flag exists so that divergence is a deliberate, reviewed choice by
+    /// whoever wires up a store, rather than something discovered by an incident.
+    pub acknowledge_hash_only_keys: bool,
+}
+
+/// Errors returned by [`MmapChained`] operations.
+#[derive(Debug)]
+pub enum MmapError {
+    /// The bucket this key hashed into found no free or matching slot within
+    /// `max_search` probes. The caller should [`MmapChained::grow_bucket`] the affected
+    /// bucket and retry.
+    NeedsGrow {
+        bucket_index: usize,
+    },
+    Io(io::Error),
+}
+
+impl From<io::Error> for MmapError {
+    fn from(err: io::Error) -> Self {
+        MmapError::Io(err)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum SlotState {
+    Empty = 0,
+    Occupied = 1,
+    Tombstone = 2,
+}
+
+/// An on-disk record. Only the key's `u64` hash is stored, not the key itself, so that
+/// `V: Copy` values can be written at fixed offsets without requiring `K: Copy` too.
+///
+/// This means two distinct keys that happen to hash to the same `u64` are
+/// indistinguishable on disk: the second `insert` silently overwrites the first's
+/// value, and `get`/`remove` return whichever one matches the hash. Unlike
+/// [`Chained`](crate::chained::Chained), [`HashMap`](crate::separate_chaining::HashMap)
+/// and [`OpenAddressed`](crate::open_addressed::OpenAddressed) — which all fall back to
+/// `K: Eq` after a hash match — `MmapChained` is not a correctness-equivalent drop-in
+/// replacement when `K`'s hash can collide, which for a 64-bit hash over an unbounded
+/// key space is always a possibility worth weighing against the memory savings.
+#[repr(C)]
+struct Record<V: Copy> {
+    hash: u64,
+    state: SlotState,
+    value: V,
+}
+
+struct Bucket<V: Copy> {
+    _file: File,
+    mmap: MmapMut,
+    capacity: usize,
+    _marker: std::marker::PhantomData<V>,
+}
+
+impl<V: Copy> Bucket<V> {
+    fn open(path: &Path, capacity: usize) -> io::Result<Self> {
+        let record_size = size_of::<Record<V>>();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.set_len((record_size * capacity) as u64)?;
+        let mmap = unsafe { MmapOptions::new().map_mut(&file)? };
+        let mut bucket = Bucket {
+            _file: file,
+            mmap,
+            capacity,
+            _marker: std::marker::PhantomData,
+        };
+        // A freshly extended file is zero-filled by the OS, which matches `SlotState::Empty`
+        // (discriminant 0), but we set it explicitly so a bucket never depends on that.
+        for slot in 0..capacity {
+            bucket.record_mut(slot).state = SlotState::Empty;
+        }
+        Ok(bucket)
+    }
+
+    fn record(&self, index: usize) -> &Record<V> {
+        let offset = index * size_of::<Record<V>>();
+        unsafe { &*(self.mmap.as_ptr().add(offset).cast::<Record<V>>()) }
+    }
+
+    fn record_mut(&mut self, index: usize) -> &mut Record<V> {
+        let offset = index * size_of::<Record<V>>();
+        unsafe { &mut *(self.mmap.as_mut_ptr().add(offset).cast::<Record<V>>()) }
+    }
+}
+
+/// A disk-backed, memory-mapped bucket store for key/value data that doesn't fit in
+/// RAM, with `insert`/`get`/`remove` methods that take keys by value like those of
+/// [`Chained`](crate::chained::Chained). Their return types differ from `Chained`'s:
+/// `insert` returns a `Result` that reports [`MmapError::NeedsGrow`] when a bucket is full,
+/// and `get`/`remove` return the value by copy rather than by reference, so call sites
+/// written against `Chained` have to change when switching to this backend.
+///
+/// Unlike [`Chained`](crate::chained::Chained), values must be `Copy` and fixed-size so
+/// records can be written at fixed offsets within a bucket's mmap'd file, and keys are
+/// not stored on disk at all — see [`Record`]'s doc comment for what that costs.
+///
+/// Within a bucket, records are placed by linear probing with wraparound starting at
+/// `hash % capacity`, the same scheme [`OpenAddressed`](crate::open_addressed::OpenAddressed)
+/// uses in memory. `insert`/`get`/`remove` all seed their scan from that same start index,
+/// so growing a bucket (which changes `capacity`, and therefore every key's start index)
+/// can't strand previously placed records.
+pub struct MmapChained<K, V: Copy, S = RandomState> {
+    buckets: Vec<Bucket<V>>,
+    config: MmapChainedConfig,
+    hasher: S,
+    len: usize,
+    _key: std::marker::PhantomData<K>,
+}
+
+impl<K, V> MmapChained<K, V, RandomState>
+where
+    K: Hash,
+    V: Copy,
+{
+    pub fn open(config: MmapChainedConfig) -> io::Result<Self> {
+        Self::open_with_hasher(config, RandomState::new())
+    }
+}
+
+impl<K, V, S> MmapChained<K, V, S>
+where
+    K: Hash,
+    V: Copy,
+    S: BuildHasher,
+{
+    pub fn open_with_hasher(config: MmapChainedConfig, hasher: S) -> io::Result<Self> {
+        if !config.acknowledge_hash_only_keys {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "MmapChainedConfig::acknowledge_hash_only_keys must be set to true: \
+                 MmapChained stores only a key's u64 hash on disk, so colliding hashes \
+                 silently merge distinct keys (see Record's doc comment); set the flag \
+                 once that tradeoff has been signed off on for this use case",
+            ));
+        }
+
+        let bucket_count = config.bucket_count.next_power_of_two();
+        let drives = if config.drives.is_empty() {
+            vec![std::env::temp_dir()]
+        } else {
+            config.drives.clone()
+        };
+
+        let mut buckets = Vec::with_capacity(bucket_count);
+        for index in 0..bucket_count {
+            let drive = &drives[index % drives.len()];
+            let path = drive.join(format!("bucket_{index}.mmap"));
+            buckets.push(Bucket::open(&path, 1)?);
+        }
+
+        Ok(MmapChained {
+            buckets,
+            config: MmapChainedConfig {
+                drives,
+                bucket_count,
+                ..config
+            },
+            hasher,
+            len: 0,
+            _key: std::marker::PhantomData,
+        })
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, MmapError> {
+        let hash = self.hasher.hash_one(&key);
+        let bucket_index = self.bucket_index(hash);
+        let capacity = self.buckets[bucket_index].capacity;
+
+        if let Some(slot) = self.find_occupied(bucket_index, hash, capacity) {
+            let old_value = self.buckets[bucket_index].record(slot).value;
+            self.buckets[bucket_index].record_mut(slot).value = value;
+            return Ok(Some(old_value));
+        }
+
+        let max_search = self.config.max_search.min(capacity);
+        self.place_in_bucket(bucket_index, hash, value, max_search)?;
+        self.len += 1;
+        Ok(None)
+    }
+
+    pub fn get(&self, key: K) -> Option<V> {
+        let hash = self.hasher.hash_one(&key);
+        let bucket_index = self.bucket_index(hash);
+        let capacity = self.buckets[bucket_index].capacity;
+        let slot = self.find_occupied(bucket_index, hash, capacity)?;
+        Some(self.buckets[bucket_index].record(slot).value)
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let hash = self.hasher.hash_one(&key);
+        let bucket_index = self.bucket_index(hash);
+        let capacity = self.buckets[bucket_index].capacity;
+        let slot = self.find_occupied(bucket_index, hash, capacity)?;
+
+        let record = self.buckets[bucket_index].record_mut(slot);
+        let value = record.value;
+        record.state = SlotState::Tombstone;
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Doubles the capacity of a bucket that returned [`MmapError::NeedsGrow`],
+    /// rewriting its backing file in place and reinserting live records.
+    pub fn grow_bucket(&mut self, bucket_index: usize) -> io::Result<()> {
+        let drive = &self.config.drives[bucket_index % self.config.drives.len()];
+        let path = drive.join(format!("bucket_{bucket_index}.mmap"));
+
+        let old_capacity = self.buckets[bucket_index].capacity;
+        let new_capacity = old_capacity * 2;
+
+        let live: Vec<(u64, V)> = (0..old_capacity)
+            .filter_map(|slot| {
+                let record = self.buckets[bucket_index].record(slot);
+                (record.state == SlotState::Occupied).then_some((record.hash, record.value))
+            })
+            .collect();
+
+        self.buckets[bucket_index] = Bucket::open(&path, new_capacity)?;
+        for (hash, value) in live {
+            self.place_in_bucket(bucket_index, hash, value, new_capacity)
+                .expect("new_capacity always has room for the bucket's previous live records");
+        }
+
+        Ok(())
+    }
+
+    /// Scans `bucket_index` for a record matching `hash`, starting at `hash % capacity`
+    /// and wrapping around, stopping early at the first empty slot: a matching record
+    /// placed by [`Self::place_in_bucket`] can never sit past the first empty slot seen
+    /// from its own start index.
+    fn find_occupied(&self, bucket_index: usize, hash: u64, scan_limit: usize) -> Option<usize> {
+        let bucket = &self.buckets[bucket_index];
+        let capacity = bucket.capacity;
+        let start = (hash % capacity as u64) as usize;
+
+        for step in 0..scan_limit {
+            let slot = (start + step) % capacity;
+            let record = bucket.record(slot);
+            match record.state {
+                SlotState::Occupied if record.hash == hash => return Some(slot),
+                SlotState::Empty => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Places `(hash, value)` in `bucket_index`, starting the probe at `hash % capacity`
+    /// and wrapping around, same as [`Self::find_occupied`], so lookups can trust that
+    /// nothing hashing here was placed further along the sequence than the first empty
+    /// slot. Bails out with [`MmapError::NeedsGrow`] if no slot is free within
+    /// `scan_limit` probes.
+    fn place_in_bucket(
+        &mut self,
+        bucket_index: usize,
+        hash: u64,
+        value: V,
+        scan_limit: usize,
+    ) -> Result<(), MmapError> {
+        let capacity = self.buckets[bucket_index].capacity;
+        let start = (hash % capacity as u64) as usize;
+
+        let mut first_tombstone = None;
+        for step in 0..scan_limit {
+            let slot = (start + step) % capacity;
+            let record = self.buckets[bucket_index].record(slot);
+            match record.state {
+                SlotState::Empty => {
+                    let target = first_tombstone.unwrap_or(slot);
+                    let record = self.buckets[bucket_index].record_mut(target);
+                    record.hash = hash;
+                    record.state = SlotState::Occupied;
+                    record.value = value;
+                    return Ok(());
+                }
+                SlotState::Tombstone if first_tombstone.is_none() => {
+                    first_tombstone = Some(slot);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(target) = first_tombstone {
+            let record = self.buckets[bucket_index].record_mut(target);
+            record.hash = hash;
+            record.state = SlotState::Occupied;
+            record.value = value;
+            return Ok(());
+        }
+
+        Err(MmapError::NeedsGrow { bucket_index })
+    }
+
+    fn bucket_index(&self, hash: u64) -> usize {
+        let bits = self.buckets.len().trailing_zeros();
+        if bits == 0 {
+            0
+        } else {
+            (hash >> (64 - bits)) as usize
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_search: usize) -> MmapChainedConfig {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT_DIR.fetch_add(1, Ordering::Relaxed);
+
+        MmapChainedConfig {
+            drives: vec![std::env::temp_dir().join(format!("mmap_chained_test_{id}"))],
+            bucket_count: 1,
+            max_search,
+            acknowledge_hash_only_keys: true,
+        }
+    }
+
+    fn open(max_search: usize) -> MmapChained<u64, u64> {
+        let cfg = config(max_search);
+        std::fs::create_dir_all(&cfg.drives[0]).unwrap();
+        MmapChained::open(cfg).unwrap()
+    }
+
+    // Buckets always start at capacity 1, so inserting more than `max_search` keys needs
+    // at least one `grow_bucket` along the way; this mirrors how a real caller handles
+    // `NeedsGrow` and keeps the test from depending on how many keys fit before growing.
+    fn insert_growing(map: &mut MmapChained<u64, u64>, key: u64, value: u64) -> Option<u64> {
+        loop {
+            match map.insert(key, value) {
+                Ok(old) => return old,
+                Err(MmapError::NeedsGrow { bucket_index }) => {
+                    map.grow_bucket(bucket_index).unwrap();
+                }
+                Err(MmapError::Io(err)) => panic!("unexpected io error: {err}"),
+            }
+        }
+    }
+
+    #[test]
+    fn open_rejects_unacknowledged_hash_only_keys() {
+        let mut cfg = config(4);
+        cfg.acknowledge_hash_only_keys = false;
+        std::fs::create_dir_all(&cfg.drives[0]).unwrap();
+
+        match MmapChained::<u64, u64>::open(cfg) {
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidInput),
+            Ok(_) => panic!("expected open to reject a config without the acknowledgment"),
+        }
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = open(4);
+
+        assert_eq!(insert_growing(&mut map, 1, 100), None);
+        assert_eq!(insert_growing(&mut map, 2, 200), None);
+        assert_eq!(map.len(), 2);
+        assert!(!map.is_empty());
+
+        assert_eq!(map.get(1), Some(100));
+        assert_eq!(map.get(2), Some(200));
+        assert_eq!(map.get(3), None);
+
+        assert_eq!(insert_growing(&mut map, 1, 111), Some(100));
+        assert_eq!(map.get(1), Some(111));
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.remove(1), Some(111));
+        assert_eq!(map.get(1), None);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.remove(1), None);
+    }
+
+    #[test]
+    fn tombstones_are_reused_on_insert() {
+        let mut map = open(2);
+
+        insert_growing(&mut map, 1, 10);
+        insert_growing(&mut map, 2, 20);
+        assert_eq!(map.remove(1), Some(10));
+
+        // With both slots previously filled and one freed by `remove`, this insert must
+        // land in the tombstoned slot rather than failing with `NeedsGrow`.
+        assert!(matches!(map.insert(3, 30), Ok(None)));
+        assert_eq!(map.get(2), Some(20));
+        assert_eq!(map.get(3), Some(30));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn grow_bucket_round_trips_every_key() {
+        let mut map = open(2);
+
+        let mut inserted = Vec::new();
+        for key in 0..20u64 {
+            insert_growing(&mut map, key, key * 10);
+            inserted.push(key);
+        }
+
+        assert_eq!(map.len(), inserted.len());
+        for key in inserted {
+            assert_eq!(map.get(key), Some(key * 10));
+        }
+    }
+}